@@ -0,0 +1,25 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use shaderc::{Compiler, ShaderKind};
+
+/// Compiles `shaders/vert.vert` to SPIR-V into `OUT_DIR` on every build, so
+/// the binary `Renderer::with_shader` loads via `include_bytes!` is always
+/// the one produced from the GLSL source checked into the repo, instead of
+/// relying on a contributor remembering to recompile it by hand.
+fn main() {
+    println!("cargo:rerun-if-changed=shaders/vert.vert");
+
+    let source =
+        fs::read_to_string("shaders/vert.vert").expect("failed to read shaders/vert.vert");
+
+    let mut compiler = Compiler::new().expect("failed to create shaderc compiler");
+    let binary = compiler
+        .compile_into_spirv(&source, ShaderKind::Vertex, "vert.vert", "main", None)
+        .expect("failed to compile shaders/vert.vert");
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    fs::write(Path::new(&out_dir).join("vert.spv"), binary.as_binary_u8())
+        .expect("failed to write compiled vert.spv");
+}
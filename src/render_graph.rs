@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wgpu::{self, TextureView};
+
+use crate::render_pass::{BoxedRenderPass, Device, RenderPass};
+
+/// Identifies the named texture slot a pass reads from or writes to. Passes
+/// are wired together by matching a consumer's input slot name against a
+/// producer's output slot name.
+pub(crate) type SlotId = &'static str;
+
+/// A single node in the [`RenderGraph`]: a pass plus the named slots it
+/// consumes and produces.
+pub(crate) struct PassNode {
+    pass: BoxedRenderPass,
+    inputs: Vec<SlotId>,
+    output: SlotId,
+}
+
+impl PassNode {
+    /// Creates a node from a pass and the slot names that connect it to its
+    /// neighbours. `output` is the slot other nodes can declare as an input
+    /// to read this pass's result.
+    pub(crate) fn new(pass: BoxedRenderPass, inputs: Vec<SlotId>, output: SlotId) -> Self {
+        PassNode {
+            pass,
+            inputs,
+            output,
+        }
+    }
+}
+
+/// Error produced when a [`RenderGraph`] cannot be scheduled.
+#[derive(Debug)]
+pub(crate) enum RenderGraphError {
+    /// The pass dependency graph contains a cycle, so no valid execution
+    /// order exists.
+    Cycle,
+    /// Two nodes declared the same output slot, so a consumer's input would
+    /// resolve to whichever producer happened to be indexed last.
+    DuplicateOutput(SlotId),
+    /// [`RenderGraph::render`] only knows how to ping-pong a single linear
+    /// chain; the resolved order isn't one (a node has more than one input,
+    /// more than one node reads the same output, or the nodes form more than
+    /// one disconnected chain).
+    NotLinear,
+    /// A node declared an input slot that no node produces, so `render`
+    /// would have no texture to resolve it to.
+    UnknownInput(SlotId),
+}
+
+impl std::fmt::Display for RenderGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderGraphError::Cycle => write!(f, "render graph contains a cycle"),
+            RenderGraphError::DuplicateOutput(slot) => {
+                write!(f, "output slot \"{}\" is produced by more than one node", slot)
+            }
+            RenderGraphError::NotLinear => write!(
+                f,
+                "render graph is not a single linear chain (branching and fan-in aren't supported by RenderGraph::render)"
+            ),
+            RenderGraphError::UnknownInput(slot) => {
+                write!(f, "input slot \"{}\" is not produced by any node", slot)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderGraphError {}
+
+struct ScratchTexture {
+    #[allow(dead_code)]
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+/// Owns an ordered chain of [`RenderPass`] effects (e.g. source -> CRT
+/// curvature -> bloom -> final blit), resolving their run order from
+/// producer/consumer slot names instead of requiring hand-wired encoders.
+/// Intermediate results are ping-ponged between two scratch textures; the
+/// last pass renders to the real `render_target` passed to [`RenderGraph::render`].
+pub(crate) struct RenderGraph {
+    device: Rc<wgpu::Device>,
+    order: Vec<PassNode>,
+    texture_format: wgpu::TextureFormat,
+    scratch: [ScratchTexture; 2],
+}
+
+impl RenderGraph {
+    /// Builds a graph from an unordered set of nodes, computing the
+    /// execution order up front. `width`/`height` size the ping-pong scratch
+    /// textures; `texture_format` must match the nodes' pipelines (see
+    /// [`crate::renderers::RendererConfig::texture_format`]).
+    pub(crate) fn new(
+        device: Device,
+        nodes: Vec<PassNode>,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> Result<Self, RenderGraphError> {
+        let order = Self::topological_sort(nodes)?;
+        let scratch = [
+            Self::create_scratch_texture(&device, width, height, texture_format),
+            Self::create_scratch_texture(&device, width, height, texture_format),
+        ];
+
+        Ok(RenderGraph {
+            device,
+            order,
+            texture_format,
+            scratch,
+        })
+    }
+
+    fn create_scratch_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> ScratchTexture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+        let view = texture.create_default_view();
+
+        ScratchTexture { texture, view }
+    }
+
+    /// Recreates the ping-pong scratch textures, e.g. after the pixel buffer
+    /// itself is resized.
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        self.scratch = [
+            Self::create_scratch_texture(&self.device, width, height, self.texture_format),
+            Self::create_scratch_texture(&self.device, width, height, self.texture_format),
+        ];
+    }
+
+    /// Kahn's algorithm: repeatedly emits nodes with in-degree 0, decrementing
+    /// their successors; a node left over once none remain means a cycle.
+    /// Also requires the result to be a single linear chain, since
+    /// [`RenderGraph::render`] only ping-pongs two scratch textures.
+    fn topological_sort(nodes: Vec<PassNode>) -> Result<Vec<PassNode>, RenderGraphError> {
+        let mut producer_of: HashMap<SlotId, usize> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            if producer_of.insert(node.output, i).is_some() {
+                return Err(RenderGraphError::DuplicateOutput(node.output));
+            }
+        }
+
+        if nodes.iter().any(|node| node.inputs.len() > 1) {
+            return Err(RenderGraphError::NotLinear);
+        }
+
+        for node in &nodes {
+            for input in &node.inputs {
+                if !producer_of.contains_key(input) {
+                    return Err(RenderGraphError::UnknownInput(*input));
+                }
+            }
+        }
+
+        let mut in_degree = vec![0usize; nodes.len()];
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        for (consumer, node) in nodes.iter().enumerate() {
+            for input in &node.inputs {
+                let producer = producer_of[input];
+                successors[producer].push(consumer);
+                in_degree[consumer] += 1;
+            }
+        }
+
+        if successors.iter().any(|succs| succs.len() > 1) {
+            return Err(RenderGraphError::NotLinear);
+        }
+
+        let mut ready: Vec<usize> = in_degree
+            .iter()
+            .enumerate()
+            .filter(|(_, &degree)| degree == 0)
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut order_indices = Vec::with_capacity(nodes.len());
+        while let Some(i) = ready.pop() {
+            order_indices.push(i);
+            for &succ in &successors[i] {
+                in_degree[succ] -= 1;
+                if in_degree[succ] == 0 {
+                    ready.push(succ);
+                }
+            }
+        }
+
+        if order_indices.len() != nodes.len() {
+            return Err(RenderGraphError::Cycle);
+        }
+
+        // Catches disconnected roots: a forest of single-node chains passes
+        // the fan-out/fan-in guards above but isn't one linear chain.
+        if !order_indices.is_empty() {
+            if !nodes[order_indices[0]].inputs.is_empty() {
+                return Err(RenderGraphError::NotLinear);
+            }
+            for window in order_indices.windows(2) {
+                let (prev, next) = (window[0], window[1]);
+                let expected = nodes[prev].output;
+                match nodes[next].inputs.first() {
+                    Some(&input) if input == expected => {}
+                    _ => return Err(RenderGraphError::NotLinear),
+                }
+            }
+        }
+
+        let mut nodes: Vec<Option<PassNode>> = nodes.into_iter().map(Some).collect();
+        Ok(order_indices
+            .into_iter()
+            .map(|i| nodes[i].take().unwrap())
+            .collect())
+    }
+
+    /// Runs every pass in resolved order, ping-ponging the two scratch
+    /// textures for intermediate results and finishing with `render_target`.
+    ///
+    /// Each node's declared input slot is resolved against the scratch
+    /// texture its producer actually wrote to, rather than assumed from its
+    /// position in `order`, so the ping-pong parity stays correct even if a
+    /// future change makes the chain skip a slot.
+    pub(crate) fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &TextureView,
+    ) {
+        let last = self.order.len().saturating_sub(1);
+        let mut produced_at: HashMap<SlotId, usize> = HashMap::new();
+
+        for (i, node) in self.order.iter_mut().enumerate() {
+            if let Some(&input) = node.inputs.first() {
+                let slot_index = *produced_at.get(&input).unwrap_or_else(|| {
+                    panic!(
+                        "render graph slot \"{}\" consumed before it was produced",
+                        input
+                    )
+                });
+                node.pass.update_bindings(&self.scratch[slot_index].view);
+            }
+
+            let output = if i == last {
+                render_target
+            } else {
+                let slot_index = i % 2;
+                produced_at.insert(node.output, slot_index);
+                &self.scratch[slot_index].view
+            };
+            node.pass.render_pass(encoder, output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    /// A `RenderPass` that does nothing, for exercising `topological_sort`
+    /// without needing a real `wgpu::Device`.
+    struct NoopPass;
+
+    impl RenderPass for NoopPass {
+        fn update_bindings(&mut self, _input_texture: &TextureView) {}
+
+        fn render_pass(&self, _encoder: &mut wgpu::CommandEncoder, _render_target: &TextureView) {}
+
+        fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "NoopPass")
+        }
+    }
+
+    fn node(inputs: Vec<SlotId>, output: SlotId) -> PassNode {
+        PassNode::new(Box::new(NoopPass), inputs, output)
+    }
+
+    #[test]
+    fn linear_chain_sorts_in_dependency_order() {
+        let nodes = vec![
+            node(vec!["a"], "b"),
+            node(vec![], "a"),
+            node(vec!["b"], "c"),
+        ];
+        let order = RenderGraph::topological_sort(nodes).expect("linear chain should sort");
+        let outputs: Vec<SlotId> = order.iter().map(|node| node.output).collect();
+        assert_eq!(outputs, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn disconnected_roots_are_rejected_as_not_linear() {
+        // Two independent nodes with no edges between them: both have
+        // in-degree 0 and no successors, so neither the fan-out nor the
+        // fan-in guard catches them, even though `render` can only ping-pong
+        // a single chain.
+        let nodes = vec![node(vec![], "a"), node(vec![], "b")];
+        let err = RenderGraph::topological_sort(nodes).unwrap_err();
+        assert!(matches!(err, RenderGraphError::NotLinear));
+    }
+
+    #[test]
+    fn cycle_is_rejected() {
+        let nodes = vec![node(vec!["b"], "a"), node(vec!["a"], "b")];
+        let err = RenderGraph::topological_sort(nodes).unwrap_err();
+        assert!(matches!(err, RenderGraphError::Cycle));
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+use std::rc::Rc;
+
+use futures::executor::block_on;
+use wgpu::{self, TextureView};
+
+use crate::render_pass::Device;
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Where a render pass's final output ends up: the visible swapchain, or an
+/// offscreen texture that can be read back to the CPU.
+pub(crate) trait RenderTarget: fmt::Debug {
+    /// The view passes should render into.
+    fn view(&self) -> &TextureView;
+}
+
+/// Renders into the surface-provided swapchain view.
+#[derive(Debug)]
+pub(crate) struct SurfaceTarget<'a> {
+    view: &'a TextureView,
+}
+
+impl<'a> SurfaceTarget<'a> {
+    pub(crate) fn new(view: &'a TextureView) -> Self {
+        SurfaceTarget { view }
+    }
+}
+
+impl<'a> RenderTarget for SurfaceTarget<'a> {
+    fn view(&self) -> &TextureView {
+        self.view
+    }
+}
+
+/// An offscreen color texture plus a CPU-visible readback buffer, for
+/// screenshots, golden-image tests, and batch frame export without a
+/// visible window.
+pub(crate) struct TextureTarget {
+    device: Rc<wgpu::Device>,
+    texture: wgpu::Texture,
+    texture_view: wgpu::TextureView,
+    readback_buffer: wgpu::Buffer,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    unpadded_bytes_per_row: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl fmt::Debug for TextureTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextureTarget")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl TextureTarget {
+    /// Creates an offscreen target of `width` x `height` pixels, sizing the
+    /// readback buffer's row stride to satisfy wgpu's 256-byte row-alignment
+    /// requirement for `copy_texture_to_buffer`. `format` must match the
+    /// pipeline rendering into this target (see
+    /// [`crate::renderers::RendererConfig::texture_format`]).
+    pub(crate) fn new(device: Device, width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+        });
+        let texture_view = texture.create_default_view();
+
+        let unpadded_bytes_per_row = width * BYTES_PER_PIXEL;
+        let padding = (wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+            - unpadded_bytes_per_row % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            size: u64::from(padded_bytes_per_row) * u64::from(height),
+            usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_READ,
+        });
+
+        TextureTarget {
+            device,
+            texture,
+            texture_view,
+            readback_buffer,
+            format,
+            width,
+            height,
+            unpadded_bytes_per_row,
+            padded_bytes_per_row,
+        }
+    }
+
+    /// Copies the rendered texture into the readback buffer. Call once per
+    /// frame, after the render pass that wrote to [`TextureTarget::view`]
+    /// and before [`TextureTarget::read_rgba`].
+    pub(crate) fn copy_to_buffer(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::BufferCopyView {
+                buffer: &self.readback_buffer,
+                offset: 0,
+                bytes_per_row: self.padded_bytes_per_row,
+                rows_per_image: self.height,
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth: 1,
+            },
+        );
+    }
+
+    /// Maps the readback buffer and returns a tightly-packed RGBA `Vec<u8>`
+    /// (row padding stripped, and B/R swapped back if `format` is a BGRA
+    /// variant) the caller can feed to the `image` crate. Blocks on the map
+    /// future, polling the device to drive it to completion.
+    pub(crate) fn read_rgba(&self) -> Vec<u8> {
+        let buffer_size = u64::from(self.padded_bytes_per_row) * u64::from(self.height);
+        let mapping_future = self.readback_buffer.map_read(0, buffer_size);
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let mapping = block_on(mapping_future).expect("failed to map readback buffer");
+        let padded = mapping.as_slice();
+
+        let swap_to_rgba = matches!(
+            self.format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        );
+
+        let mut pixels = Vec::with_capacity((self.unpadded_bytes_per_row * self.height) as usize);
+        for row in padded.chunks(self.padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..self.unpadded_bytes_per_row as usize]);
+        }
+
+        if swap_to_rgba {
+            for pixel in pixels.chunks_mut(BYTES_PER_PIXEL as usize) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        self.readback_buffer.unmap();
+
+        pixels
+    }
+}
+
+impl RenderTarget for TextureTarget {
+    fn view(&self) -> &TextureView {
+        &self.texture_view
+    }
+}
@@ -1,89 +1,382 @@
 use byteorder::{ByteOrder, LittleEndian};
+use futures::executor::block_on;
+use log::warn;
 use std::fmt;
 use std::rc::Rc;
 use wgpu::{self, TextureView};
 
 use crate::render_pass::{BoxedRenderPass, Device, Queue, RenderPass};
 
+/// Sample counts wgpu pipelines may request MSAA with.
+const VALID_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// Formats a `wgpu::Surface` may be configured with across platforms.
+const SURFACE_COMPATIBLE_FORMATS: [wgpu::TextureFormat; 4] = [
+    wgpu::TextureFormat::Bgra8UnormSrgb,
+    wgpu::TextureFormat::Bgra8Unorm,
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+    wgpu::TextureFormat::Rgba8Unorm,
+];
+
+/// Ensures `format` is both a format the surface can be configured with and
+/// the one the surface was actually created with, so a misconfigured format
+/// fails loudly here instead of silently rendering a black window.
+fn validate_surface_format(
+    format: wgpu::TextureFormat,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::TextureFormat {
+    assert!(
+        SURFACE_COMPATIBLE_FORMATS.contains(&format),
+        "{:?} is not a format a surface can be configured with",
+        format,
+    );
+    assert_eq!(
+        format, surface_format,
+        "requested texture format {:?} does not match the surface's format {:?}",
+        format, surface_format,
+    );
+
+    format
+}
+
+/// Clamps `requested` to a sample count the adapter actually supports,
+/// falling back to 1 (no MSAA) with a logged warning when it isn't one of
+/// `adapter_supported`.
+fn validate_sample_count(requested: u32, adapter_supported: &[u32]) -> u32 {
+    if VALID_SAMPLE_COUNTS.contains(&requested) && adapter_supported.contains(&requested) {
+        requested
+    } else {
+        warn!(
+            "requested MSAA sample count {} is not supported by this adapter (supported: {:?}); falling back to 1",
+            requested, adapter_supported,
+        );
+        1
+    }
+}
+
+/// How the pixel buffer is mapped onto the output surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScalingMode {
+    /// Stretch to fill the surface, ignoring aspect ratio.
+    Stretch,
+    /// Scale uniformly to fit inside the surface, letterboxing the rest.
+    AspectFit,
+    /// Scale by the largest whole-number multiple that still fits.
+    IntegerScale,
+}
+
+/// Byte size of the scaling matrix uniform at bind group slot 2.
+const MATRIX_SIZE: wgpu::BufferAddress = std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress;
+
+/// A user-supplied uniform or texture bound alongside the built-in texture,
+/// sampler, and scaling matrix slots in a [`Renderer::with_shader`]
+/// pipeline. Owning the resource (rather than borrowing it from the caller)
+/// lets `Renderer` rebuild its bind group on [`RenderPass::update_bindings`]
+/// without dropping these extra bindings.
+#[derive(Debug)]
+pub(crate) enum ExtraBinding {
+    Buffer {
+        binding: u32,
+        visibility: wgpu::ShaderStage,
+        buffer: wgpu::Buffer,
+        size: wgpu::BufferAddress,
+    },
+    Texture {
+        binding: u32,
+        visibility: wgpu::ShaderStage,
+        view: wgpu::TextureView,
+    },
+    Sampler {
+        binding: u32,
+        visibility: wgpu::ShaderStage,
+        sampler: wgpu::Sampler,
+    },
+}
+
+impl ExtraBinding {
+    fn layout_binding(&self) -> wgpu::BindGroupLayoutBinding {
+        match self {
+            ExtraBinding::Buffer {
+                binding,
+                visibility,
+                ..
+            } => wgpu::BindGroupLayoutBinding {
+                binding: *binding,
+                visibility: *visibility,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            },
+            ExtraBinding::Texture {
+                binding,
+                visibility,
+                ..
+            } => wgpu::BindGroupLayoutBinding {
+                binding: *binding,
+                visibility: *visibility,
+                ty: wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
+                },
+            },
+            ExtraBinding::Sampler {
+                binding,
+                visibility,
+                ..
+            } => wgpu::BindGroupLayoutBinding {
+                binding: *binding,
+                visibility: *visibility,
+                ty: wgpu::BindingType::Sampler,
+            },
+        }
+    }
+
+    fn binding(&self) -> wgpu::Binding<'_> {
+        match self {
+            ExtraBinding::Buffer {
+                binding,
+                buffer,
+                size,
+                ..
+            } => wgpu::Binding {
+                binding: *binding,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer,
+                    range: 0..*size,
+                },
+            },
+            ExtraBinding::Texture { binding, view, .. } => wgpu::Binding {
+                binding: *binding,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            ExtraBinding::Sampler {
+                binding, sampler, ..
+            } => wgpu::Binding {
+                binding: *binding,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        }
+    }
+}
+
+/// The 4x4 (column-major) transform that positions the fullscreen quad in
+/// clip space for a [`ScalingMode`]. Uploaded as a vertex-stage uniform so
+/// the scaling shader stays a plain fullscreen-quad draw.
+#[derive(Debug, Clone, Copy)]
+struct ScalingMatrix {
+    matrix: [f32; 16],
+}
+
+impl ScalingMatrix {
+    /// Computes the matrix for `mode` given the pixel buffer's dimensions
+    /// and the current surface dimensions. Call this again with the new
+    /// surface size on window resize and write the result into the existing
+    /// uniform buffer (as [`Renderer::resize`] does).
+    fn new(mode: ScalingMode, buffer_size: (u32, u32), surface_size: (u32, u32)) -> Self {
+        let (buffer_width, buffer_height) = (buffer_size.0 as f32, buffer_size.1 as f32);
+        let (surface_width, surface_height) = (surface_size.0 as f32, surface_size.1 as f32);
+
+        let (scale_x, scale_y) = match mode {
+            ScalingMode::Stretch => (1.0, 1.0),
+            ScalingMode::AspectFit => {
+                let scale = (surface_width / buffer_width).min(surface_height / buffer_height);
+                (
+                    scale * buffer_width / surface_width,
+                    scale * buffer_height / surface_height,
+                )
+            }
+            ScalingMode::IntegerScale => {
+                let scale = (surface_width / buffer_width)
+                    .min(surface_height / buffer_height)
+                    .floor()
+                    .max(1.0);
+                (
+                    scale * buffer_width / surface_width,
+                    scale * buffer_height / surface_height,
+                )
+            }
+        };
+
+        #[rustfmt::skip]
+        let matrix = [
+            scale_x, 0.0,     0.0, 0.0,
+            0.0,     scale_y, 0.0, 0.0,
+            0.0,     0.0,     1.0, 0.0,
+            0.0,     0.0,     0.0, 1.0,
+        ];
+
+        ScalingMatrix { matrix }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.matrix.as_ptr() as *const u8,
+                self.matrix.len() * std::mem::size_of::<f32>(),
+            )
+        }
+    }
+}
+
 /// Renderer implements [`RenderPass`].
 #[derive(Debug)]
 pub(crate) struct Renderer {
     device: Rc<wgpu::Device>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    matrix_buffer: wgpu::Buffer,
+    scaling_mode: ScalingMode,
+    buffer_size: (u32, u32),
+    attachment_size: (u32, u32),
+    /// Whether `attachment_size` was constructed equal to `surface_size`,
+    /// i.e. this is a final/standalone pass rather than an intermediate
+    /// `RenderGraph` node. `resize` only knows the new surface size, so it
+    /// can only keep the MSAA framebuffer's size correct for this case.
+    tracks_surface_size: bool,
+    extra: Vec<ExtraBinding>,
     bind_group: wgpu::BindGroup,
     render_pipeline: wgpu::RenderPipeline,
+    clear_color: wgpu::Color,
+    multisampled_framebuffer: Option<wgpu::TextureView>,
+    sample_count: u32,
+    texture_format: wgpu::TextureFormat,
+}
+
+/// Settings for [`Renderer::factory`]/[`Renderer::with_shader`], grouped into
+/// a struct rather than passed positionally so that same-typed parameters
+/// (`buffer_size` vs. `surface_size`, `texture_format` vs. `surface_format`)
+/// must be named at the call site instead of risking a silent transposition.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RendererConfig<'a> {
+    pub(crate) buffer_size: (u32, u32),
+    pub(crate) surface_size: (u32, u32),
+    /// Dimensions of the texture this pass renders into: `surface_size` for
+    /// a standalone/final pass, or the scratch texture's size for an
+    /// intermediate [`crate::render_graph::RenderGraph`] node. Sizes the
+    /// MSAA framebuffer only; `surface_size` still drives the scaling matrix.
+    pub(crate) attachment_size: (u32, u32),
+    pub(crate) scaling_mode: ScalingMode,
+    pub(crate) clear_color: wgpu::Color,
+    pub(crate) sample_count: u32,
+    pub(crate) adapter_supported_sample_counts: &'a [u32],
+    pub(crate) texture_format: wgpu::TextureFormat,
+    pub(crate) surface_format: wgpu::TextureFormat,
+    pub(crate) filter_mode: wgpu::FilterMode,
 }
 
 impl Renderer {
     /// Factory function for generating `RenderPass` trait objects.
     pub(crate) fn factory(
         device: Device,
-        _queue: Queue,
+        queue: Queue,
         texture_view: &TextureView,
+        config: RendererConfig,
     ) -> BoxedRenderPass {
-        let vert_spv = include_bytes!("../shaders/vert.spv");
-        let mut vert = Vec::new();
-        vert.resize_with(
-            vert_spv.len() / std::mem::size_of::<u32>(),
-            Default::default,
-        );
-        LittleEndian::read_u32_into(vert_spv, &mut vert);
-
         let frag_spv = include_bytes!("../shaders/frag.spv");
-        let mut frag = Vec::new();
-        frag.resize_with(
-            frag_spv.len() / std::mem::size_of::<u32>(),
-            Default::default,
-        );
-        LittleEndian::read_u32_into(frag_spv, &mut frag);
+        Self::with_shader(device, queue, texture_view, frag_spv, Vec::new(), config)
+    }
+
+    /// Builds a `RenderPass` from a caller-supplied fragment shader (SPIR-V
+    /// bytes) plus any extra bindings it needs for its own uniforms or
+    /// textures. The source texture and sampler are always bound at slots 0
+    /// and 1, the scaling matrix at slot 2; `extra` entries are appended
+    /// starting at whatever slot the caller assigns them (3+ is conventional).
+    ///
+    /// This lets callers drop in a CRT/scanline shader, a palette-remap LUT
+    /// pass, or a sharp-bilinear filter without forking the crate, while
+    /// still reusing the sampler + bind-group plumbing below. `extra`
+    /// resources are owned by the returned `Renderer` so that
+    /// `update_bindings` can rebuild the bind group (e.g. when chained in a
+    /// [`crate::render_graph::RenderGraph`]) without losing them.
+    pub(crate) fn with_shader(
+        device: Device,
+        _queue: Queue,
+        texture_view: &TextureView,
+        frag_spv: &[u8],
+        extra: Vec<ExtraBinding>,
+        config: RendererConfig,
+    ) -> BoxedRenderPass {
+        let RendererConfig {
+            buffer_size,
+            surface_size,
+            attachment_size,
+            scaling_mode,
+            clear_color,
+            sample_count,
+            adapter_supported_sample_counts,
+            texture_format,
+            surface_format,
+            filter_mode,
+        } = config;
+        let sample_count = validate_sample_count(sample_count, adapter_supported_sample_counts);
+        let texture_format = validate_surface_format(texture_format, surface_format);
+        // Compiled from `../shaders/vert.vert` by `build.rs`, which reads
+        // `u_scaling_matrix` at set 0 binding 2 and applies it to the
+        // fullscreen quad position.
+        let vert_spv = include_bytes!(concat!(env!("OUT_DIR"), "/vert.spv"));
+        let vert = spirv_words(vert_spv);
+        let frag = spirv_words(frag_spv);
 
         let vs_module = device.create_shader_module(&vert);
         let fs_module = device.create_shader_module(&frag);
 
-        // Create a texture sampler with nearest neighbor
+        // Create a texture sampler: `Nearest` for crisp pixels, `Linear` for
+        // smoothed scaling.
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Nearest,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: filter_mode,
             lod_min_clamp: 0.0,
             lod_max_clamp: 1.0,
             compare_function: wgpu::CompareFunction::Always,
         });
 
-        // Create bind group
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            bindings: &[
-                wgpu::BindGroupLayoutBinding {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: wgpu::BindingType::SampledTexture {
-                        multisampled: false,
-                        dimension: wgpu::TextureViewDimension::D2,
-                    },
-                },
-                wgpu::BindGroupLayoutBinding {
-                    binding: 1,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler,
-                },
-            ],
-        });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            bindings: &[
-                wgpu::Binding {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
-                },
-                wgpu::Binding {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
+        // The scaling matrix positions the fullscreen quad; the vertex
+        // shader reads it to implement `ScalingMode`.
+        let scaling_matrix = ScalingMatrix::new(scaling_mode, buffer_size, surface_size);
+        let matrix_buffer = device.create_buffer_with_data(
+            scaling_matrix.as_bytes(),
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_WRITE,
+        );
+
+        // Create bind group, with any caller-supplied uniforms/textures
+        // appended after the texture, sampler, and scaling matrix slots.
+        let mut layout_bindings = vec![
+            wgpu::BindGroupLayoutBinding {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::SampledTexture {
+                    multisampled: false,
+                    dimension: wgpu::TextureViewDimension::D2,
                 },
-            ],
+            },
+            wgpu::BindGroupLayoutBinding {
+                binding: 1,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Sampler,
+            },
+            wgpu::BindGroupLayoutBinding {
+                binding: 2,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::UniformBuffer { dynamic: false },
+            },
+        ];
+        layout_bindings.extend(extra.iter().map(ExtraBinding::layout_binding));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            bindings: &layout_bindings,
         });
 
+        let bind_group = Self::build_bind_group(
+            &device,
+            &bind_group_layout,
+            texture_view,
+            &sampler,
+            &matrix_buffer,
+            &extra,
+        );
+
         // Create pipeline
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             bind_group_layouts: &[&bind_group_layout],
@@ -107,7 +400,7 @@ impl Renderer {
             }),
             primitive_topology: wgpu::PrimitiveTopology::TriangleList,
             color_states: &[wgpu::ColorStateDescriptor {
-                format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                format: texture_format,
                 color_blend: wgpu::BlendDescriptor::REPLACE,
                 alpha_blend: wgpu::BlendDescriptor::REPLACE,
                 write_mask: wgpu::ColorWrite::ALL,
@@ -115,31 +408,173 @@ impl Renderer {
             depth_stencil_state: None,
             index_format: wgpu::IndexFormat::Uint16,
             vertex_buffers: &[],
-            sample_count: 1,
+            sample_count,
             sample_mask: !0,
             alpha_to_coverage_enabled: false,
         });
 
+        // When MSAA is enabled, render into a multisampled intermediate
+        // texture and let wgpu resolve it into the real render target on
+        // store; otherwise render straight into the render target as before.
+        // Sized to `attachment_size` (the texture this pass actually writes
+        // to), not `surface_size`, so a pass chained as an intermediate
+        // `RenderGraph` node gets an MSAA attachment matching its
+        // buffer-sized scratch resolve target instead of the final surface.
+        let multisampled_framebuffer = if sample_count > 1 {
+            Some(Self::create_multisampled_framebuffer(
+                &device,
+                attachment_size,
+                sample_count,
+                texture_format,
+            ))
+        } else {
+            None
+        };
+
         Box::new(Renderer {
             device,
+            bind_group_layout,
+            sampler,
+            matrix_buffer,
+            scaling_mode,
+            buffer_size,
+            attachment_size,
+            tracks_surface_size: attachment_size == surface_size,
+            extra,
             bind_group,
             render_pipeline,
+            clear_color,
+            multisampled_framebuffer,
+            sample_count,
+            texture_format,
+        })
+    }
+
+    /// Builds the bind group for `texture_view` at slot 0, reusing `sampler`,
+    /// `matrix_buffer`, and `extra` for the remaining slots. Shared between
+    /// initial construction and [`RenderPass::update_bindings`] so a pass
+    /// chained in a [`crate::render_graph::RenderGraph`] keeps sampling its
+    /// own uniforms after its input texture changes.
+    fn build_bind_group(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        texture_view: &TextureView,
+        sampler: &wgpu::Sampler,
+        matrix_buffer: &wgpu::Buffer,
+        extra: &[ExtraBinding],
+    ) -> wgpu::BindGroup {
+        let mut bindings = vec![
+            wgpu::Binding {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::Binding {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::Binding {
+                binding: 2,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: matrix_buffer,
+                    range: 0..MATRIX_SIZE,
+                },
+            },
+        ];
+        bindings.extend(extra.iter().map(ExtraBinding::binding));
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            bindings: &bindings,
         })
     }
+
+    fn create_multisampled_framebuffer(
+        device: &wgpu::Device,
+        surface_size: (u32, u32),
+        sample_count: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> wgpu::TextureView {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: surface_size.0,
+                height: surface_size.1,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        });
+
+        texture.create_default_view()
+    }
+
+    /// Recomputes the scaling matrix for a new surface size and recreates
+    /// the multisampled framebuffer (a no-op for the latter when MSAA isn't
+    /// enabled). Call on every window resize, or `AspectFit`/`IntegerScale`
+    /// keep letterboxing for the old surface size.
+    ///
+    /// Only valid on a final/standalone pass, whose attachment is the
+    /// surface itself; an intermediate `RenderGraph` node's attachment is
+    /// sized from the pixel buffer instead and doesn't track `surface_size`.
+    pub(crate) fn resize(&mut self, surface_size: (u32, u32)) {
+        debug_assert!(
+            self.tracks_surface_size,
+            "Renderer::resize called on a pass whose attachment isn't the surface"
+        );
+
+        if self.sample_count > 1 {
+            self.multisampled_framebuffer = Some(Self::create_multisampled_framebuffer(
+                &self.device,
+                surface_size,
+                self.sample_count,
+                self.texture_format,
+            ));
+        }
+        self.attachment_size = surface_size;
+
+        let scaling_matrix = ScalingMatrix::new(self.scaling_mode, self.buffer_size, surface_size);
+        let mapping_future = self.matrix_buffer.map_write(0, MATRIX_SIZE);
+        self.device.poll(wgpu::Maintain::Wait);
+        let mut mapping =
+            block_on(mapping_future).expect("failed to map matrix buffer for writing");
+        mapping
+            .as_slice()
+            .copy_from_slice(scaling_matrix.as_bytes());
+        self.matrix_buffer.unmap();
+    }
 }
 
 impl RenderPass for Renderer {
-    fn update_bindings(&mut self, _input_texture: &TextureView) {}
+    fn update_bindings(&mut self, input_texture: &TextureView) {
+        self.bind_group = Self::build_bind_group(
+            &self.device,
+            &self.bind_group_layout,
+            input_texture,
+            &self.sampler,
+            &self.matrix_buffer,
+            &self.extra,
+        );
+    }
 
     fn render_pass(&self, encoder: &mut wgpu::CommandEncoder, render_target: &TextureView) {
-        // Draw the updated texture to the render target
+        // Draw the updated texture to the render target, clearing the
+        // margin left by letterboxing (if any) to `clear_color`. When MSAA
+        // is enabled, render into the multisampled framebuffer and resolve
+        // it into `render_target` on store.
+        let (attachment, resolve_target) = match &self.multisampled_framebuffer {
+            Some(framebuffer) => (framebuffer, Some(render_target)),
+            None => (render_target, None),
+        };
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: render_target,
-                resolve_target: None,
+                attachment,
+                resolve_target,
                 load_op: wgpu::LoadOp::Clear,
                 store_op: wgpu::StoreOp::Store,
-                clear_color: wgpu::Color::BLACK,
+                clear_color: self.clear_color,
             }],
             depth_stencil_attachment: None,
         });
@@ -151,4 +586,186 @@ impl RenderPass for Renderer {
     fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
-}
\ No newline at end of file
+}
+
+/// Converts raw little-endian SPIR-V bytes (as produced by `shaderc`/`glslc`)
+/// into the `u32` words wgpu expects.
+fn spirv_words(spv: &[u8]) -> Vec<u32> {
+    let mut words = Vec::new();
+    words.resize_with(spv.len() / std::mem::size_of::<u32>(), Default::default);
+    LittleEndian::read_u32_into(spv, &mut words);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn scale_factors(
+        mode: ScalingMode,
+        buffer_size: (u32, u32),
+        surface_size: (u32, u32),
+    ) -> (f32, f32) {
+        let matrix = ScalingMatrix::new(mode, buffer_size, surface_size).matrix;
+        (matrix[0], matrix[5])
+    }
+
+    fn assert_approx_eq(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < EPSILON,
+            "expected {}, got {}",
+            expected,
+            actual,
+        );
+    }
+
+    #[test]
+    fn stretch_always_fills_surface() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::Stretch, (320, 240), (800, 300));
+        assert_approx_eq(scale_x, 1.0);
+        assert_approx_eq(scale_y, 1.0);
+    }
+
+    #[test]
+    fn aspect_fit_matching_aspect_ratio_fills_surface() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::AspectFit, (320, 240), (640, 480));
+        assert_approx_eq(scale_x, 1.0);
+        assert_approx_eq(scale_y, 1.0);
+    }
+
+    #[test]
+    fn aspect_fit_wider_surface_letterboxes_sides() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::AspectFit, (320, 240), (800, 300));
+        assert_approx_eq(scale_y, 1.0);
+        assert_approx_eq(scale_x, 0.5);
+    }
+
+    #[test]
+    fn aspect_fit_taller_surface_letterboxes_top_and_bottom() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::AspectFit, (320, 240), (300, 800));
+        assert_approx_eq(scale_x, 1.0);
+        assert_approx_eq(scale_y, 0.28125);
+    }
+
+    #[test]
+    fn integer_scale_rounds_down_to_whole_multiple() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::IntegerScale, (320, 240), (700, 500));
+        assert_approx_eq(scale_x, 2.0 * 320.0 / 700.0);
+        assert_approx_eq(scale_y, 2.0 * 240.0 / 500.0);
+    }
+
+    #[test]
+    fn integer_scale_clamps_to_one_when_surface_is_smaller_than_buffer() {
+        let (scale_x, scale_y) = scale_factors(ScalingMode::IntegerScale, (320, 240), (100, 100));
+        assert_approx_eq(scale_x, 320.0 / 100.0);
+        assert_approx_eq(scale_y, 240.0 / 100.0);
+    }
+
+    // Exercises `AspectFit` end-to-end through the real vertex shader,
+    // rather than only `ScalingMatrix::new`'s math, so a pipeline/shader
+    // mismatch (e.g. a stale `vert.spv`) fails a test instead of only
+    // showing up as a distorted image at runtime. Skips rather than fails
+    // when no wgpu adapter is available, since this environment may not
+    // have a GPU to drive.
+    #[test]
+    fn aspect_fit_letterboxes_the_rendered_output() {
+        let adapter = block_on(wgpu::Adapter::request(
+            &wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::Default,
+                backends: wgpu::BackendBit::PRIMARY,
+            },
+            wgpu::BackendBit::PRIMARY,
+        ));
+        let adapter = match adapter {
+            Some(adapter) => adapter,
+            None => {
+                eprintln!("skipping: no wgpu adapter available in this environment");
+                return;
+            }
+        };
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            extensions: wgpu::Extensions {
+                anisotropic_filtering: false,
+            },
+            limits: wgpu::Limits::default(),
+        });
+        let device = Rc::new(device);
+        let queue = Rc::new(queue);
+
+        let buffer_size = (4, 2);
+        let surface_size = (8, 2);
+        let clear_color = wgpu::Color {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        // Used for the source texture, the `TextureTarget`, and the pipeline
+        // alike, so they all agree.
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+        let source_texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: buffer_size.0,
+                height: buffer_size.1,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED,
+        });
+        let source_view = source_texture.create_default_view();
+
+        let target = crate::render_target::TextureTarget::new(
+            Rc::clone(&device),
+            surface_size.0,
+            surface_size.1,
+            format,
+        );
+
+        let pass = Renderer::factory(
+            Rc::clone(&device),
+            Rc::clone(&queue),
+            &source_view,
+            RendererConfig {
+                buffer_size,
+                surface_size,
+                attachment_size: surface_size,
+                scaling_mode: ScalingMode::AspectFit,
+                clear_color,
+                sample_count: 1,
+                adapter_supported_sample_counts: &[1],
+                texture_format: format,
+                surface_format: format,
+                filter_mode: wgpu::FilterMode::Nearest,
+            },
+        );
+
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        pass.render_pass(&mut encoder, target.view());
+        target.copy_to_buffer(&mut encoder);
+        queue.submit(&[encoder.finish()]);
+
+        let pixels = target.read_rgba();
+        let pixel_at = |x: u32, y: u32| -> &[u8] {
+            let row_start = (y * surface_size.0 * 4) as usize;
+            let col_start = row_start + (x * 4) as usize;
+            &pixels[col_start..col_start + 4]
+        };
+
+        // AspectFit on a 4x2 buffer inside an 8x2 surface scales to
+        // (0.5, 1.0): the quad covers only the middle half of the width, so
+        // the outer two columns on each side are left as the letterbox
+        // `clear_color` rather than sampling the source texture.
+        for y in 0..surface_size.1 {
+            assert_eq!(pixel_at(0, y), [255, 0, 0, 255]);
+            assert_eq!(pixel_at(surface_size.0 - 1, y), [255, 0, 0, 255]);
+        }
+    }
+}